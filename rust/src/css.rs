@@ -0,0 +1,438 @@
+//! CSS and inline-style minification and normalization.
+//!
+//! Today Cutesy treats `<style>` content and `style="..."` attribute
+//! values as opaque text, so stylesheets embedded in templates go
+//! unformatted. This module closes that gap with a small CSS tokenizer
+//! that strips comments, collapses insignificant whitespace, drops the
+//! trailing semicolon in a block, normalizes zero lengths (`0px` ->
+//! `0`) and hex color casing, and either minifies the result or
+//! pretty-prints one declaration per line.
+
+use pyo3::prelude::*;
+
+const INDENT_UNIT: &str = "    ";
+
+/// Formats a full stylesheet (the contents of a `<style>` block).
+/// Handles nested at-rules (`@media`, `@supports`, ...) by recursing
+/// into their bodies.
+#[pyfunction]
+pub fn format_css_fast(css: &str, minify: bool) -> PyResult<String> {
+    let stripped = strip_comments(css);
+    Ok(format_rules(&stripped, minify, 0))
+}
+
+/// Formats the declaration list found in a `style="..."` attribute
+/// value, which has no selector or braces around it.
+#[pyfunction]
+pub fn attr_style(value: &str, minify: bool) -> PyResult<String> {
+    let stripped = strip_comments(value);
+    Ok(format_declarations(&stripped, minify, 0))
+}
+
+/// Strips `/* ... */` comments, skipping over quoted strings so a `/*`
+/// or `*/` that merely appears inside a CSS string (`content: "a /* b
+/// */ c"`) isn't mistaken for a real comment delimiter.
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut chars = css.char_indices().peekable();
+    let bytes = css.as_bytes();
+    let mut quote: Option<char> = None;
+
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            Some(q) => {
+                out.push(c);
+                if c == q {
+                    quote = None;
+                }
+            }
+            None if c == '/' && bytes.get(i + 1) == Some(&b'*') => {
+                if let Some(end) = css[i + 2..].find("*/") {
+                    let skip_to = i + 2 + end + 2;
+                    while let Some(&(j, _)) = chars.peek() {
+                        if j >= skip_to {
+                            break;
+                        }
+                        chars.next();
+                    }
+                } else {
+                    break; // Unterminated comment: drop the rest of the input.
+                }
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+/// Formats a sequence of `selector { declarations }` rules, `;`-terminated
+/// statement at-rules (`@import "reset.css";`, `@charset "UTF-8";`), and
+/// nested at-rule groups, at a given indent depth. Content that's neither
+/// — trailing text after the last rule, or an unterminated block — is
+/// kept via [`push_statement`] (whitespace collapsed outside quoted
+/// strings, `;` appended) rather than dropped, since silently deleting
+/// user CSS is not an option for a formatter.
+fn format_rules(source: &str, minify: bool, depth: usize) -> String {
+    let indent = INDENT_UNIT.repeat(depth);
+    let mut out = String::new();
+    let mut rest = source;
+
+    loop {
+        let brace = find_top_level(rest, b'{');
+        let semi = find_top_level(rest, b';');
+
+        // A `;` before the next top-level `{` (or no `{` left at all)
+        // means a statement at-rule ends here, not a rule block.
+        let semi_is_first = match (semi, brace) {
+            (Some(s), Some(b)) => s < b,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if semi_is_first {
+            let semi = semi.unwrap();
+            push_statement(&mut out, &rest[..semi], minify, &indent);
+            rest = &rest[semi + 1..];
+            continue;
+        }
+
+        let Some(brace) = brace else {
+            // No more blocks or statements: whatever's left (whitespace,
+            // or trailing content after the last rule) is kept as a
+            // statement instead of being dropped.
+            push_statement(&mut out, rest, minify, &indent);
+            break;
+        };
+
+        let prelude = collapse_whitespace_quote_aware(&rest[..brace]);
+        if prelude.is_empty() {
+            rest = &rest[brace + 1..];
+            continue;
+        }
+        let Some(close) = find_matching_brace(&rest[brace..]) else {
+            // Unterminated block: keep the remaining text instead of
+            // dropping it.
+            push_statement(&mut out, rest, minify, &indent);
+            break;
+        };
+        let body = &rest[brace + 1..brace + close];
+
+        if minify {
+            out.push_str(&prelude);
+            out.push('{');
+        } else {
+            out.push_str(&indent);
+            out.push_str(&prelude);
+            out.push_str(" {\n");
+        }
+
+        if find_top_level(body, b'{').is_some() {
+            out.push_str(&format_rules(body, minify, depth + 1));
+        } else {
+            out.push_str(&format_declarations(body, minify, depth + 1));
+        }
+
+        if minify {
+            out.push('}');
+        } else {
+            out.push_str(&indent);
+            out.push_str("}\n");
+        }
+
+        rest = &rest[brace + close + 1..];
+    }
+
+    out
+}
+
+/// Appends a single `;`-terminated statement (e.g. a statement at-rule,
+/// or trailing content with no statement at all) to `out`. Whitespace-only
+/// input is dropped silently; anything else is kept, quote-aware-collapsed
+/// but otherwise untouched, so content the formatter doesn't understand
+/// still survives the round trip.
+fn push_statement(out: &mut String, statement: &str, minify: bool, indent: &str) {
+    let collapsed = collapse_whitespace_quote_aware(statement);
+    if collapsed.is_empty() {
+        return;
+    }
+    if minify {
+        out.push_str(&collapsed);
+        out.push(';');
+    } else {
+        out.push_str(indent);
+        out.push_str(&collapsed);
+        out.push_str(";\n");
+    }
+}
+
+/// Formats a flat list of `property: value;` declarations at a given
+/// indent depth. The trailing semicolon of the block is always dropped;
+/// in non-minify mode each declaration gets its own indented line.
+fn format_declarations(body: &str, minify: bool, depth: usize) -> String {
+    let indent = INDENT_UNIT.repeat(depth);
+    let decls = split_top_level(body, b';');
+    let mut formatted = Vec::new();
+
+    for decl in decls {
+        let trimmed = decl.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        // The property name never contains a quoted string in valid CSS,
+        // so a plain top-level search for `:` is safe here; it's the
+        // *value* that needs quote-aware handling, done in
+        // `normalize_value` below.
+        let Some(colon) = trimmed.find(':') else {
+            formatted.push(collapse_whitespace(trimmed));
+            continue;
+        };
+        let property = collapse_whitespace(trimmed[..colon].trim());
+        // Custom properties (`--MyVar`) are case-sensitive identifiers;
+        // lowercasing one would change which `var(--MyVar)` references
+        // still resolve to it. Only built-in property names, which are
+        // case-insensitive, get lowercased.
+        let property = if property.starts_with("--") { property } else { property.to_ascii_lowercase() };
+        let value = normalize_value(trimmed[colon + 1..].trim());
+        formatted.push(if minify { format!("{property}:{value}") } else { format!("{property}: {value}") });
+    }
+
+    if minify {
+        formatted.join(";")
+    } else {
+        let last = formatted.len().saturating_sub(1);
+        formatted
+            .iter()
+            .enumerate()
+            .map(|(i, decl)| if i == last { format!("{indent}{decl}\n") } else { format!("{indent}{decl};\n") })
+            .collect()
+    }
+}
+
+/// Normalizes a declaration value: collapses whitespace, lowercases hex
+/// colors, and collapses zero lengths like `0px`/`0em` down to a bare
+/// `0`. Quoted strings (`content: "a  b"`, `url('a b')`) are copied
+/// through byte-for-byte, since whitespace and casing inside a CSS
+/// string are significant and none of the other normalizations apply to
+/// their contents either.
+fn normalize_value(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = String::with_capacity(value.len());
+    let mut i = 0;
+    let mut pending_space = false;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+
+        if b.is_ascii_whitespace() {
+            if !out.is_empty() {
+                pending_space = true;
+            }
+            i += 1;
+            continue;
+        }
+
+        if pending_space {
+            out.push(' ');
+            pending_space = false;
+        }
+
+        if b == b'"' || b == b'\'' {
+            let quote = b;
+            let start = i;
+            i += 1;
+            while i < bytes.len() && bytes[i] != quote {
+                i += 1;
+            }
+            if i < bytes.len() {
+                i += 1; // include the closing quote
+            }
+            out.push_str(&value[start..i]);
+            continue;
+        }
+
+        let start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'"' && bytes[i] != b'\'' {
+            i += 1;
+        }
+        out.push_str(&zero_unit_word(&lowercase_hex_colors(&value[start..i])));
+    }
+
+    out
+}
+
+fn lowercase_hex_colors(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = String::with_capacity(value.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'#' {
+            let mut j = i + 1;
+            while j < bytes.len() && bytes[j].is_ascii_hexdigit() {
+                j += 1;
+            }
+            let hex_len = j - (i + 1);
+            if matches!(hex_len, 3 | 4 | 6 | 8) {
+                out.push('#');
+                out.push_str(&value[i + 1..j].to_ascii_lowercase());
+                i = j;
+                continue;
+            }
+        }
+        // Push this character (not necessarily ASCII) and advance by its
+        // full UTF-8 width.
+        let ch = value[i..].chars().next().expect("i is a valid char boundary");
+        out.push(ch);
+        i += ch.len_utf8();
+    }
+    out
+}
+
+/// Collapses a zero length — a `0<unit>` or `0.0<unit>` token (optionally
+/// signed) whose unit is a CSS length unit — down to a bare `0`, keeping
+/// a trailing comma from a comma-separated list. Non-zero values,
+/// unitless values, other units (`0s`, `0deg`, `0%`), and words with
+/// anything else after the unit (`0px)`) are left untouched.
+fn zero_unit_word(word: &str) -> String {
+    const LENGTH_UNITS: [&str; 15] =
+        ["px", "em", "rem", "ex", "ch", "vw", "vh", "vmin", "vmax", "cm", "mm", "q", "in", "pt", "pc"];
+    let (core, suffix) = match word.strip_suffix(',') {
+        Some(stripped) => (stripped, ","),
+        None => (word, ""),
+    };
+
+    let unit_start = core.find(|c: char| c.is_ascii_alphabetic() || c == '%').unwrap_or(core.len());
+    let (number, unit) = core.split_at(unit_start);
+    if !LENGTH_UNITS.contains(&unit.to_ascii_lowercase().as_str()) {
+        return word.to_string();
+    }
+
+    match number.parse::<f64>() {
+        Ok(n) if n == 0.0 => format!("0{suffix}"),
+        _ => word.to_string(),
+    }
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Like [`collapse_whitespace`], but leaves whitespace inside quoted
+/// strings alone (`@import url("a   b.css")`, `[data-foo="a   b"]`),
+/// since a selector or at-rule prelude can itself contain a quoted
+/// string whose internal spacing is significant.
+fn collapse_whitespace_quote_aware(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = String::with_capacity(s.len());
+    let mut i = 0;
+    let mut pending_space = false;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+
+        if b.is_ascii_whitespace() {
+            if !out.is_empty() {
+                pending_space = true;
+            }
+            i += 1;
+            continue;
+        }
+
+        if pending_space {
+            out.push(' ');
+            pending_space = false;
+        }
+
+        if b == b'"' || b == b'\'' {
+            let quote = b;
+            let start = i;
+            i += 1;
+            while i < bytes.len() && bytes[i] != quote {
+                i += 1;
+            }
+            if i < bytes.len() {
+                i += 1; // include the closing quote
+            }
+            out.push_str(&s[start..i]);
+            continue;
+        }
+
+        let ch = s[i..].chars().next().expect("i is a valid char boundary");
+        out.push(ch);
+        i += ch.len_utf8();
+    }
+
+    out
+}
+
+/// Finds the first occurrence of `needle` that isn't nested inside
+/// parentheses, brackets, or a quoted string.
+fn find_top_level(s: &str, needle: u8) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut quote: Option<u8> = None;
+    for (i, &b) in s.as_bytes().iter().enumerate() {
+        match quote {
+            Some(q) => {
+                if b == q {
+                    quote = None;
+                }
+            }
+            None => match b {
+                b'"' | b'\'' => quote = Some(b),
+                b'(' | b'[' => depth += 1,
+                b')' | b']' => depth -= 1,
+                _ if depth == 0 && b == needle => return Some(i),
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+/// Splits `s` on every top-level occurrence of `sep`, skipping ones
+/// nested inside parentheses, brackets, or quotes.
+fn split_top_level(s: &str, sep: u8) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = s;
+    let mut start_offset = 0;
+    while let Some(pos) = find_top_level(&rest[start_offset..], sep) {
+        let abs = start_offset + pos;
+        parts.push(&rest[..abs]);
+        rest = &rest[abs + 1..];
+        start_offset = 0;
+    }
+    parts.push(rest);
+    parts
+}
+
+/// Given a slice beginning with `{`, returns the byte offset (relative
+/// to the slice) of its matching `}`.
+fn find_matching_brace(s: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut quote: Option<u8> = None;
+    for (i, &b) in s.as_bytes().iter().enumerate() {
+        match quote {
+            Some(q) => {
+                if b == q {
+                    quote = None;
+                }
+            }
+            None => match b {
+                b'"' | b'\'' => quote = Some(b),
+                b'{' => depth += 1,
+                b'}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+    None
+}
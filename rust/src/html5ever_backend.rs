@@ -0,0 +1,204 @@
+//! Optional html5ever-based parse backend for spec-compliant tokenization.
+//!
+//! `parse_loop_fast` is a hand-written single-pass tokenizer tuned
+//! for speed and lenient error recovery. Some callers instead need strict
+//! WHATWG-conformant handling of foreign content, implied tags, and
+//! character references — cases the fast path intentionally doesn't
+//! chase. This module drives [`html5ever`]'s battle-tested streaming
+//! tokenizer and adapts its callbacks into the same [`Token`] shape
+//! `parse_loop_fast` produces, byte spans included, so the formatter can
+//! still do byte-exact rewrites regardless of which backend produced the
+//! tokens. `parse_with_html5ever` is exposed as a separate function next
+//! to `parse_loop_fast`; nothing in this crate switches between the two.
+
+use html5ever::buffer_queue::BufferQueue;
+use html5ever::tendril::{StrTendril, TendrilSink};
+use html5ever::tokenizer::{
+    CharacterTokens, CommentToken, DoctypeToken, EndTag, ParseError, PIToken, StartTag, TagToken,
+    Token as Html5everToken, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use pyo3::prelude::*;
+
+use crate::tokenizer::{Token, TokenKind};
+
+/// Parses `source` using html5ever's spec-compliant tokenizer instead of
+/// the fast hand-written one, returning the same `Token` shape so both
+/// backends are drop-in replacements for each other from Python.
+#[pyfunction]
+pub fn parse_with_html5ever(source: &str) -> PyResult<Vec<Token>> {
+    let sink = Adapter::new(source);
+    let mut tokenizer = Tokenizer::new(sink, TokenizerOpts::default());
+
+    let mut queue = BufferQueue::default();
+    queue.push_back(StrTendril::from(source));
+    let _ = tokenizer.feed(&mut queue);
+    tokenizer.end();
+
+    Ok(tokenizer.sink.tokens)
+}
+
+/// Bridges html5ever's `TokenSink` callback interface to our `Token`
+/// list. html5ever reports tokens without byte offsets, so the adapter
+/// keeps its own cursor (`consumed`) into `source` and, for tokens that
+/// correspond to literal markup (tags, doctypes), re-scans from that
+/// cursor to find the matching `>` and recover the real span. Tokens
+/// html5ever synthesizes rather than reads from the source — implied
+/// tags, foster-parented content — have no literal markup to find, so
+/// they're emitted as a zero-width span at the cursor without advancing
+/// it, leaving the cursor correct for whatever comes next.
+struct Adapter<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    total_len: usize,
+    consumed: usize,
+}
+
+impl<'a> Adapter<'a> {
+    fn new(source: &'a str) -> Self {
+        Self { source, tokens: Vec::new(), total_len: source.len(), consumed: 0 }
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize, end: usize, name: Option<String>, attrs: Vec<(String, String)>) {
+        self.tokens.push(Token { kind, start, end, name, attrs, message: None });
+    }
+
+    fn push_error(&mut self, start: usize, end: usize, message: String) {
+        self.tokens.push(Token { kind: TokenKind::Error, start, end, name: None, attrs: Vec::new(), message: Some(message) });
+    }
+
+    /// If `start` is the byte offset of a literal `<...>` construct in
+    /// `source` (a tag or doctype), returns the offset just past its
+    /// closing `>`, honoring quoted attribute/public-id values so a `>`
+    /// inside a quoted string doesn't end the scan early. Returns `None`
+    /// when there's no `<` at `start` at all (an implied token with no
+    /// source text) or the construct is unterminated.
+    fn locate_markup_end(&self, start: usize) -> Option<usize> {
+        let bytes = self.source.as_bytes();
+        if bytes.get(start) != Some(&b'<') {
+            return None;
+        }
+        let mut quote: Option<u8> = None;
+        let mut i = start + 1;
+        while i < bytes.len() {
+            match quote {
+                Some(q) => {
+                    if bytes[i] == q {
+                        quote = None;
+                    }
+                }
+                None => match bytes[i] {
+                    b'"' | b'\'' => quote = Some(bytes[i]),
+                    b'>' => return Some(i + 1),
+                    _ => {}
+                },
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// Advances `consumed` to `end` and pushes a token spanning
+    /// `[start, end)`, or — when this token had no literal markup to
+    /// locate (`end` is `None`) — pushes a zero-width token at `start`
+    /// and leaves `consumed` untouched for whatever comes next.
+    fn push_markup(&mut self, kind: TokenKind, start: usize, end: Option<usize>, name: Option<String>, attrs: Vec<(String, String)>) {
+        let span_end = end.unwrap_or(start);
+        self.push(kind, start, span_end, name, attrs);
+        if let Some(end) = end {
+            self.consumed = end;
+        }
+    }
+
+    /// Finds the true end of a character-data run starting at `start`.
+    /// html5ever hands back the *decoded* text (character references
+    /// resolved, CRLF normalized to LF), so its length can't be added to
+    /// `start` to recover the source span — a text node containing
+    /// `&amp;` is 5 source bytes but decodes to 1. Per the tokenizer spec,
+    /// character data always ends just before the next literal `<` (which
+    /// starts a new token, markup or not), so re-scanning the source for
+    /// that byte recovers the real extent; with none left, the run
+    /// extends to the end of the source.
+    fn locate_text_end(&self, start: usize) -> usize {
+        self.source[start..].find('<').map_or(self.total_len, |i| start + i)
+    }
+
+    /// Finds the true end of a comment starting at `start`, for the same
+    /// reason `locate_text_end` exists: html5ever's decoded comment text
+    /// can't be used to derive the source span. A real `<!--...-->`
+    /// comment ends at its literal `-->`; a bogus comment (`<!foo>`,
+    /// `<?foo>`, `</>`-style) instead ends at the next literal `>`, same
+    /// as `locate_markup_end`. Returns `None` when the construct never
+    /// closes in the source.
+    fn locate_comment_end(&self, start: usize) -> Option<usize> {
+        if self.source[start..].starts_with("<!--") {
+            self.source[start + 4..].find("-->").map(|i| start + 4 + i + 3)
+        } else {
+            self.source[start..].find('>').map(|i| start + i + 1)
+        }
+    }
+}
+
+impl<'a> TokenSink for Adapter<'a> {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Html5everToken, _line_number: u64) -> TokenSinkResult<()> {
+        // Spans aren't reported directly, so every token starts at the
+        // cursor `consumed`; each arm below locates the token's end in
+        // the source and moves the cursor forward to it.
+        // This is exact for back-to-back tokens fed from a single
+        // in-memory chunk, which is how `parse_with_html5ever` always
+        // calls this adapter.
+        let start = self.consumed;
+
+        match token {
+            CharacterTokens(_) => {
+                let end = self.locate_text_end(start);
+                self.push(TokenKind::Text, start, end, None, Vec::new());
+                self.consumed = end;
+            }
+            TagToken(tag) => {
+                let name = tag.name.to_string();
+                let attrs = tag
+                    .attrs
+                    .into_iter()
+                    .map(|attr| (attr.name.local.to_string(), attr.value.to_string()))
+                    .collect();
+                let kind = match tag.kind {
+                    StartTag => TokenKind::StartTag,
+                    EndTag => TokenKind::EndTag,
+                };
+                let end = self.locate_markup_end(start);
+                self.push_markup(kind, start, end, Some(name), attrs);
+            }
+            CommentToken(_) => {
+                // An unterminated comment still consumed source text (it's
+                // not an implied token), it just never found its closer,
+                // so — unlike `push_markup`'s implied-token case — the
+                // cursor advances to the end of the source rather than
+                // staying put.
+                let end = self.locate_comment_end(start).unwrap_or(self.total_len);
+                self.push(TokenKind::Comment, start, end, None, Vec::new());
+                self.consumed = end;
+            }
+            DoctypeToken(_) => {
+                let end = self.locate_markup_end(start);
+                self.push_markup(TokenKind::Doctype, start, end, None, Vec::new());
+            }
+            PIToken(pi) => {
+                let end = start + pi.data.len();
+                self.push(TokenKind::ProcessingInstruction, start, end, None, Vec::new());
+                self.consumed = end;
+            }
+            ParseError(message) => {
+                self.push_error(start, start, message.to_string());
+            }
+            _ => {}
+        }
+
+        TokenSinkResult::Continue
+    }
+
+    fn end(&mut self) {
+        self.consumed = self.total_len;
+    }
+}
@@ -11,6 +11,20 @@
 
 use pyo3::prelude::*;
 
+mod css;
+mod html5ever_backend;
+mod pattern_scanner;
+mod text;
+mod tokenizer;
+mod url;
+
+use css::{attr_style, format_css_fast};
+use html5ever_backend::parse_with_html5ever;
+use pattern_scanner::PatternScanner;
+use text::handle_data_fast;
+use tokenizer::{parse_loop_fast, Token, TokenKind};
+use url::normalize_url_attr;
+
 /// Example stub function to verify the extension loads correctly.
 #[pyfunction]
 fn hello_from_rust() -> PyResult<String> {
@@ -21,10 +35,17 @@ fn hello_from_rust() -> PyResult<String> {
 #[pymodule]
 fn cutesy_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(hello_from_rust, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_loop_fast, m)?)?;
+    m.add_function(wrap_pyfunction!(handle_data_fast, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_with_html5ever, m)?)?;
+    m.add_function(wrap_pyfunction!(format_css_fast, m)?)?;
+    m.add_function(wrap_pyfunction!(attr_style, m)?)?;
+    m.add_function(wrap_pyfunction!(normalize_url_attr, m)?)?;
+    m.add_class::<Token>()?;
+    m.add_class::<TokenKind>()?;
+    m.add_class::<PatternScanner>()?;
     Ok(())
 }
 
 // Future functions to implement:
-// - handle_data_fast() - Optimized text processing
 // - attr_sort_fast() - Optimized attribute sorting
-// - parse_loop_fast() - Optimized parsing loop
@@ -0,0 +1,294 @@
+//! Multi-pattern scanner for template/entity detection in text nodes.
+//!
+//! `handle_data()` tests every text node against a regex alternation of
+//! template delimiters (`{{ }}`, `{% %}`), HTML entities that need
+//! escaping, and known placeholder markers. That's effectively a
+//! multi-pattern search, so instead of a regex we build a compact
+//! double-array Aho-Corasick automaton: a trie over the registered
+//! patterns, failure links computed by BFS, and output sets collected
+//! along those links, all packed into BASE/CHECK arrays for cache-
+//! friendly, branch-light lookup. A linter builds one `PatternScanner`
+//! per ruleset and reuses it across every file it checks.
+
+use std::collections::HashMap;
+
+use pyo3::prelude::*;
+
+/// A trie node used only while building the automaton, before its final
+/// position in the packed BASE/CHECK arrays is known.
+#[derive(Default)]
+struct BuildNode {
+    children: HashMap<u8, usize>,
+    /// Ids (insertion order) of patterns that end exactly at this node.
+    ends: Vec<usize>,
+}
+
+/// A reusable multi-pattern matcher backed by a double-array
+/// Aho-Corasick automaton.
+///
+/// Construct once per ruleset with the list of patterns to look for
+/// (each pattern's id is its index in that list), then call
+/// [`find_overlapping`](PatternScanner::find_overlapping) or
+/// [`find`](PatternScanner::find) as many times as needed across files.
+#[pyclass]
+pub struct PatternScanner {
+    /// `base[state] + (byte as usize + 1)` is the candidate array index
+    /// for `state`'s child on `byte`; ownership is confirmed by `check`.
+    base: Vec<i64>,
+    /// `check[index] == state` confirms `index` really is a child of
+    /// `state`; unowned slots hold `-1`.
+    check: Vec<i64>,
+    /// Failure link per state, to the longest proper suffix of that
+    /// state's path that is itself a path from the root.
+    fail: Vec<usize>,
+    /// Pattern ids completed at each state, including those inherited
+    /// through failure links.
+    output: Vec<Vec<usize>>,
+    pattern_lens: Vec<usize>,
+}
+
+const EMPTY: i64 = -1;
+const ROOT: usize = 0;
+
+#[pymethods]
+impl PatternScanner {
+    #[new]
+    fn new(patterns: Vec<String>) -> Self {
+        PatternScanner::build(&patterns)
+    }
+
+    /// Returns every match of every registered pattern, including ones
+    /// that overlap each other (e.g. both `{` and `{%` matching at the
+    /// same start position).
+    fn find_overlapping(&self, text: &str) -> Vec<(usize, usize, usize)> {
+        let bytes = text.as_bytes();
+        let mut state = ROOT;
+        let mut matches = Vec::new();
+
+        for (i, &b) in bytes.iter().enumerate() {
+            state = self.advance(state, b);
+            for &pattern_id in &self.output[state] {
+                let end = i + 1;
+                let start = end - self.pattern_lens[pattern_id];
+                matches.push((start, end, pattern_id));
+            }
+        }
+
+        matches
+    }
+
+    /// Returns a non-overlapping set of matches: scanning left to right,
+    /// the longest match starting at the earliest available position
+    /// wins, and any match that would overlap it is dropped.
+    fn find(&self, text: &str) -> Vec<(usize, usize, usize)> {
+        let mut matches = self.find_overlapping(text);
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then((b.1 - b.0).cmp(&(a.1 - a.0))));
+
+        let mut result = Vec::new();
+        let mut next_allowed = 0;
+        for m in matches {
+            if m.0 >= next_allowed {
+                next_allowed = m.1;
+                result.push(m);
+            }
+        }
+        result
+    }
+}
+
+impl PatternScanner {
+    fn build(patterns: &[String]) -> Self {
+        let mut nodes: Vec<BuildNode> = vec![BuildNode::default()];
+
+        for (pattern_id, pattern) in patterns.iter().enumerate() {
+            let mut node = ROOT;
+            for &b in pattern.as_bytes() {
+                node = match nodes[node].children.get(&b) {
+                    Some(&child) => child,
+                    None => {
+                        nodes.push(BuildNode::default());
+                        let child = nodes.len() - 1;
+                        nodes[node].children.insert(b, child);
+                        child
+                    }
+                };
+            }
+            nodes[node].ends.push(pattern_id);
+        }
+
+        let pattern_lens = patterns.iter().map(|p| p.len()).collect();
+
+        let mut scanner = PatternScanner {
+            base: vec![1; 1],
+            check: vec![EMPTY; 1],
+            fail: vec![ROOT; 1],
+            output: vec![Vec::new()],
+            pattern_lens,
+        };
+        scanner.pack(&nodes);
+        scanner
+    }
+
+    /// Packs the build-time trie into BASE/CHECK double arrays, then
+    /// computes failure links and output sets by BFS over the packed
+    /// states (shallower states are always enqueued, and thus finalized,
+    /// before deeper ones, which is what lets the failure/output passes
+    /// below rely on a state's failure target already being complete).
+    fn pack(&mut self, nodes: &[BuildNode]) {
+        let mut final_index = vec![usize::MAX; nodes.len()];
+        final_index[ROOT] = ROOT;
+        self.output[ROOT] = nodes[ROOT].ends.clone();
+
+        let mut queue = std::collections::VecDeque::new();
+        queue.push_back(ROOT);
+
+        while let Some(temp_u) = queue.pop_front() {
+            let u = final_index[temp_u];
+            if nodes[temp_u].children.is_empty() {
+                continue;
+            }
+
+            let labels: Vec<u8> = nodes[temp_u].children.keys().copied().collect();
+            let base = self.find_free_base(u, &labels);
+            self.base[u] = base as i64;
+
+            for (&label, &temp_child) in &nodes[temp_u].children {
+                let child_index = base + label as usize + 1;
+                self.ensure_len(child_index + 1);
+                self.check[child_index] = u as i64;
+                final_index[temp_child] = child_index;
+                self.output[child_index] = nodes[temp_child].ends.clone();
+
+                let v = self.fail[u];
+                self.fail[child_index] = self.failure_target(v, label, child_index);
+                let inherited = self.output[self.fail[child_index]].clone();
+                self.output[child_index].extend(inherited);
+
+                queue.push_back(temp_child);
+            }
+        }
+    }
+
+    /// Finds the failure target for a freshly packed child reached from
+    /// `v` (the failure link of the child's parent) on `label`,
+    /// walking failure links toward the root as needed. `self_index` is
+    /// excluded so a state never fails to itself.
+    fn failure_target(&self, mut v: usize, label: u8, self_index: usize) -> usize {
+        loop {
+            if let Some(next) = self.lookup(v, label) {
+                if next != self_index {
+                    return next;
+                }
+            }
+            if v == ROOT {
+                return ROOT;
+            }
+            v = self.fail[v];
+        }
+    }
+
+    /// Looks up the packed transition from `state` on `byte`, confirming
+    /// ownership via `check`.
+    fn lookup(&self, state: usize, byte: u8) -> Option<usize> {
+        let index = self.base[state] as usize + byte as usize + 1;
+        if index < self.check.len() && self.check[index] == state as i64 {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Follows failure links until `byte` has a packed transition,
+    /// falling back to the root when nothing matches at all.
+    fn advance(&self, mut state: usize, byte: u8) -> usize {
+        loop {
+            if let Some(next) = self.lookup(state, byte) {
+                return next;
+            }
+            if state == ROOT {
+                return ROOT;
+            }
+            state = self.fail[state];
+        }
+    }
+
+    /// Finds the smallest base (>= 1) such that every label in `labels`
+    /// maps to a free slot, relative to `state`.
+    fn find_free_base(&mut self, _state: usize, labels: &[u8]) -> usize {
+        let mut base = 1;
+        loop {
+            let fits = labels.iter().all(|&label| {
+                let index = base + label as usize + 1;
+                index >= self.check.len() || self.check[index] == EMPTY
+            });
+            if fits {
+                return base;
+            }
+            base += 1;
+        }
+    }
+
+    fn ensure_len(&mut self, len: usize) {
+        if self.base.len() < len {
+            self.base.resize(len, 1);
+            self.check.resize(len, EMPTY);
+            self.fail.resize(len, ROOT);
+            self.output.resize(len, Vec::new());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(words: &[&str]) -> PatternScanner {
+        PatternScanner::new(words.iter().map(|w| w.to_string()).collect())
+    }
+
+    #[test]
+    fn overlapping_reports_every_pattern_that_matches() {
+        // The textbook Aho-Corasick example: "he", "she", "his", "hers"
+        // against "ushers" should surface the three overlapping matches
+        // ("his" does not occur in it).
+        let scanner = patterns(&["he", "she", "his", "hers"]);
+        let mut matches = scanner.find_overlapping("ushers");
+        matches.sort();
+        assert_eq!(matches, vec![(1, 4, 1), (2, 4, 0), (2, 6, 3)]);
+    }
+
+    #[test]
+    fn find_picks_the_leftmost_longest_non_overlapping_match() {
+        let scanner = patterns(&["he", "she", "his", "hers"]);
+        // "she" (1..4) starts earliest, so it wins even though "hers"
+        // (2..6) is longer; "hers" overlaps "she" and is dropped.
+        assert_eq!(scanner.find("ushers"), vec![(1, 4, 1)]);
+    }
+
+    #[test]
+    fn overlapping_patterns_like_brace_and_brace_percent_both_match() {
+        let scanner = patterns(&["{", "{%", "{{"]);
+        let mut matches = scanner.find_overlapping("a {% b {{ c");
+        matches.sort();
+        assert_eq!(matches, vec![(2, 3, 0), (2, 4, 1), (7, 8, 0), (7, 9, 2), (8, 9, 0)]);
+    }
+
+    #[test]
+    fn find_is_non_overlapping_and_longest_at_each_start() {
+        let scanner = patterns(&["a", "ab", "bc", "c"]);
+        assert_eq!(scanner.find("zzabcxx"), vec![(2, 4, 1), (4, 5, 3)]);
+    }
+
+    #[test]
+    fn no_patterns_means_no_matches() {
+        let scanner = patterns(&[]);
+        assert!(scanner.find_overlapping("hello").is_empty());
+        assert!(scanner.find("hello").is_empty());
+    }
+
+    #[test]
+    fn no_match_in_text_means_no_matches() {
+        let scanner = patterns(&["zzz"]);
+        assert!(scanner.find("hello").is_empty());
+    }
+}
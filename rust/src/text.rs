@@ -0,0 +1,191 @@
+//! SIMD-accelerated text normalization for `handle_data_fast`.
+//!
+//! Replaces the regex-based whitespace collapsing that profiling flags as
+//! ~15% of runtime. The happy path scans the text in 16-byte strides,
+//! comparing each lane against the ASCII whitespace set (space, tab, CR,
+//! LF, form feed) to build a bitmask, then uses that mask to collapse
+//! whitespace runs to a single space while trimming the leading and
+//! trailing edges. Because every whitespace byte of interest is ASCII
+//! (< 0x80), scanning at the byte level is safe even inside multi-byte
+//! UTF-8 sequences: continuation bytes are always >= 0x80 and can never
+//! match, so they pass through untouched. Any trailing partial chunk
+//! (fewer than 16 bytes) falls back to a scalar loop, as does the whole
+//! input on targets without the required intrinsics.
+
+use pyo3::prelude::*;
+
+const LANE_WIDTH: usize = 16;
+
+/// Normalizes whitespace in `text`, collapsing runs to a single space and
+/// trimming the ends. When `preserve` is true (content inside `<pre>`,
+/// `<textarea>`, `<script>`, or `<style>`), the text is returned
+/// unchanged.
+#[pyfunction]
+pub fn handle_data_fast(text: &str, preserve: bool) -> PyResult<String> {
+    if preserve {
+        return Ok(text.to_string());
+    }
+    Ok(normalize_whitespace(text.as_bytes()))
+}
+
+#[cfg(target_arch = "x86_64")]
+fn normalize_whitespace(bytes: &[u8]) -> String {
+    if is_x86_feature_detected!("sse2") {
+        // SAFETY: guarded by the runtime feature check above.
+        unsafe { normalize_whitespace_sse2(bytes) }
+    } else {
+        normalize_whitespace_scalar(bytes)
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn normalize_whitespace(bytes: &[u8]) -> String {
+    normalize_whitespace_scalar(bytes)
+}
+
+/// Scalar fallback: collapses whitespace runs and trims the ends byte by
+/// byte. Used as the whole implementation when SIMD intrinsics aren't
+/// available; the SSE2 path finishes its final partial lane with the same
+/// `collapse_into` step.
+fn normalize_whitespace_scalar(bytes: &[u8]) -> String {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut pending_space = false;
+    let mut started = false;
+    collapse_into(bytes, &mut out, &mut pending_space, &mut started);
+
+    // SAFETY: `out` only ever contains bytes copied verbatim from the
+    // valid UTF-8 `bytes` slice, plus ASCII space, so it is valid UTF-8.
+    unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Shared scalar collapsing step: appends the normalized form of `bytes`
+/// to `out`, carrying `pending_space`/`started` across calls so a
+/// whitespace run split across a SIMD lane boundary still collapses to
+/// exactly one space.
+fn collapse_into(bytes: &[u8], out: &mut Vec<u8>, pending_space: &mut bool, started: &mut bool) {
+    for &b in bytes {
+        if is_ascii_whitespace(b) {
+            if *started {
+                *pending_space = true;
+            }
+        } else {
+            if *pending_space {
+                out.push(b' ');
+                *pending_space = false;
+            }
+            out.push(b);
+            *started = true;
+        }
+    }
+}
+
+#[inline]
+fn is_ascii_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n' | 0x0C)
+}
+
+/// SSE2 striped scan: processes 16 bytes per iteration, building a
+/// whitespace bitmask per lane and collapsing runs from it. Falls back to
+/// the scalar loop for the trailing partial chunk.
+#[cfg(target_arch = "x86_64")]
+unsafe fn normalize_whitespace_sse2(bytes: &[u8]) -> String {
+    use std::arch::x86_64::*;
+
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut pending_space = false;
+    let mut started = false;
+    let mut i = 0;
+
+    let space = _mm_set1_epi8(b' ' as i8);
+    let tab = _mm_set1_epi8(b'\t' as i8);
+    let cr = _mm_set1_epi8(b'\r' as i8);
+    let lf = _mm_set1_epi8(b'\n' as i8);
+    let ff = _mm_set1_epi8(0x0C_i8);
+
+    while i + LANE_WIDTH <= bytes.len() {
+        let chunk = _mm_loadu_si128(bytes.as_ptr().add(i) as *const __m128i);
+        let mask = _mm_or_si128(
+            _mm_or_si128(_mm_cmpeq_epi8(chunk, space), _mm_cmpeq_epi8(chunk, tab)),
+            _mm_or_si128(
+                _mm_cmpeq_epi8(chunk, cr),
+                _mm_or_si128(_mm_cmpeq_epi8(chunk, lf), _mm_cmpeq_epi8(chunk, ff)),
+            ),
+        );
+        let bitmask = _mm_movemask_epi8(mask) as u32;
+
+        for lane in 0..LANE_WIDTH {
+            let b = bytes[i + lane];
+            if bitmask & (1 << lane) != 0 {
+                if started {
+                    pending_space = true;
+                }
+            } else {
+                if pending_space {
+                    out.push(b' ');
+                    pending_space = false;
+                }
+                out.push(b);
+                started = true;
+            }
+        }
+
+        i += LANE_WIDTH;
+    }
+
+    // Trailing partial chunk: finish with the scalar loop, continuing the
+    // collapsing state we've already built up so a whitespace run split
+    // across the boundary still collapses correctly.
+    collapse_into(&bytes[i..], &mut out, &mut pending_space, &mut started);
+
+    // SAFETY: every byte pushed above is either copied verbatim from the
+    // valid UTF-8 input or an ASCII space we inserted ourselves.
+    String::from_utf8_unchecked(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(text: &str) -> String {
+        normalize_whitespace(text.as_bytes())
+    }
+
+    #[test]
+    fn collapses_internal_runs_and_trims_the_ends() {
+        assert_eq!(normalize("  hello   world  "), "hello world");
+        assert_eq!(normalize("a\n\n\nb"), "a b");
+        assert_eq!(normalize("   "), "");
+        assert_eq!(normalize(""), "");
+    }
+
+    #[test]
+    fn leaves_already_normalized_text_unchanged() {
+        assert_eq!(normalize("nochange"), "nochange");
+    }
+
+    #[test]
+    fn collapses_a_whitespace_run_split_across_the_16_byte_lane_boundary() {
+        // "0123456789abcdef" is exactly one SSE2 lane; the space run
+        // starts in the last byte of the first lane and continues into
+        // the second, which is exactly the case `collapse_into`'s shared
+        // state exists to handle correctly.
+        let text = "0123456789abcde    f";
+        assert_eq!(normalize(text), "0123456789abcde f");
+    }
+
+    #[test]
+    fn handles_input_longer_than_one_lane_with_trailing_partial_chunk() {
+        let text = "0123456789abcdef0123456789abcdef trailing  ";
+        assert_eq!(normalize(text), "0123456789abcdef0123456789abcdef trailing");
+    }
+
+    #[test]
+    fn is_safe_on_multi_byte_utf8_around_whitespace() {
+        assert_eq!(normalize("héllo   wörld  "), "héllo wörld");
+    }
+
+    #[test]
+    fn preserve_flag_short_circuits_normalization() {
+        assert_eq!(handle_data_fast("  keep  as is  ", true).unwrap(), "  keep  as is  ");
+    }
+}
@@ -0,0 +1,425 @@
+//! Streaming HTML tokenizer with error recovery.
+//!
+//! This is the Rust replacement for the Python `goahead()` main parsing
+//! loop. It runs a single pass over the source text, character by
+//! character, and emits a `Token` for every construct it recognizes
+//! (start tags, end tags, text runs, comments, doctypes, and processing
+//! instructions). Unlike a strict parser, it never aborts: malformed
+//! markup produces an `Error` token describing what was expected and
+//! what was found, and the scanner resynchronizes at the nearest
+//! sensible boundary so the rest of the file still gets tokenized. This
+//! lets the linter surface every problem in a file in one pass and keeps
+//! formatting usable on invalid HTML.
+
+use pyo3::prelude::*;
+
+/// The kind of markup construct a [`Token`] represents.
+#[pyclass(eq, eq_int)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    StartTag,
+    EndTag,
+    Text,
+    Comment,
+    Doctype,
+    ProcessingInstruction,
+    Error,
+}
+
+/// A single tokenized piece of markup.
+///
+/// `start`/`end` are byte offsets into the original source so callers can
+/// do byte-exact rewrites. `name` and `attrs` are only populated for
+/// `StartTag`/`EndTag` tokens; `attrs` holds each attribute in document
+/// order, with its name lowercased and its value as the raw, unescaped
+/// slice from the source.
+/// `message` is only populated for `Error` tokens.
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+    pub name: Option<String>,
+    pub attrs: Vec<(String, String)>,
+    pub message: Option<String>,
+}
+
+impl Token {
+    fn tag(kind: TokenKind, start: usize, end: usize, name: String, attrs: Vec<(String, String)>) -> Self {
+        Self { kind, start, end, name: Some(name), attrs, message: None }
+    }
+
+    fn simple(kind: TokenKind, start: usize, end: usize) -> Self {
+        Self { kind, start, end, name: None, attrs: Vec::new(), message: None }
+    }
+
+    fn error(start: usize, end: usize, message: String) -> Self {
+        Self { kind: TokenKind::Error, start, end, name: None, attrs: Vec::new(), message: Some(message) }
+    }
+}
+
+/// Parses `source` into a flat list of tokens, recovering from malformed
+/// markup instead of raising.
+#[pyfunction]
+pub fn parse_loop_fast(source: &str) -> PyResult<Vec<Token>> {
+    Ok(Tokenizer::new(source).run())
+}
+
+/// Internal cursor-driven scanner. Not exposed to Python directly; all
+/// state lives here so `parse_loop_fast` stays a thin entry point.
+struct Tokenizer<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    tokens: Vec<Token>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(source: &'a str) -> Self {
+        Self { src: source, bytes: source.as_bytes(), pos: 0, tokens: Vec::new() }
+    }
+
+    fn run(mut self) -> Vec<Token> {
+        while self.pos < self.bytes.len() {
+            if self.bytes[self.pos] == b'<' {
+                self.scan_markup();
+            } else {
+                self.scan_text();
+            }
+        }
+        self.tokens
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.pos + offset).copied()
+    }
+
+    /// Consumes a run of non-`<` bytes as a `Text` token.
+    fn scan_text(&mut self) {
+        let start = self.pos;
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'<' {
+            self.pos += 1;
+        }
+        self.tokens.push(Token::simple(TokenKind::Text, start, self.pos));
+    }
+
+    /// Dispatches on what follows a `<`, recovering when nothing
+    /// recognizable is found.
+    fn scan_markup(&mut self) {
+        let start = self.pos;
+        match self.peek_at(1) {
+            Some(b'/') => self.scan_end_tag(start),
+            Some(b'!') => self.scan_declaration(start),
+            Some(b'?') => self.scan_processing_instruction(start),
+            Some(c) if c.is_ascii_alphabetic() => self.scan_start_tag(start),
+            _ => {
+                // A lone `<` not followed by a name, `/`, `!`, or `?` is
+                // not markup at all per the resynchronization rule: treat
+                // it as a single character of literal text and continue
+                // scanning from there.
+                self.pos += 1;
+                self.tokens.push(Token::simple(TokenKind::Text, start, self.pos));
+            }
+        }
+    }
+
+    fn scan_start_tag(&mut self, start: usize) {
+        self.pos += 1; // consume '<'
+        let name_start = self.pos;
+        while self.pos < self.bytes.len() && is_tag_name_byte(self.bytes[self.pos]) {
+            self.pos += 1;
+        }
+        let name = self.src[name_start..self.pos].to_ascii_lowercase();
+
+        let mut attrs = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek_at(0) {
+                None => {
+                    self.tokens.push(Token::error(
+                        start,
+                        self.pos,
+                        format!("unterminated start tag <{name}>: expected '>', found end of input"),
+                    ));
+                    return;
+                }
+                Some(b'>') => {
+                    self.pos += 1;
+                    self.tokens.push(Token::tag(TokenKind::StartTag, start, self.pos, name, attrs));
+                    return;
+                }
+                Some(b'/') if self.peek_at(1) == Some(b'>') => {
+                    self.pos += 2;
+                    self.tokens.push(Token::tag(TokenKind::StartTag, start, self.pos, name, attrs));
+                    return;
+                }
+                Some(c) if is_tag_name_byte(c) => {
+                    attrs.push(self.scan_attribute());
+                }
+                Some(_) => {
+                    // Bogus attribute punctuation (e.g. a stray `=` or
+                    // quote with no name). Resynchronize by closing the
+                    // tag at the next `>` or newline, whichever comes
+                    // first, rather than aborting the whole parse.
+                    let recovered_at = self.pos;
+                    self.recover_to_tag_end();
+                    if self.pos < self.bytes.len() && self.bytes[self.pos] == b'>' {
+                        self.pos += 1;
+                    }
+                    self.tokens.push(Token::error(
+                        start,
+                        recovered_at + 1,
+                        format!(
+                            "bogus attribute in <{name}>: expected attribute name or '>', found '{}'",
+                            self.bytes[recovered_at] as char
+                        ),
+                    ));
+                    self.tokens.push(Token::tag(TokenKind::StartTag, start, self.pos, name, attrs));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn scan_attribute(&mut self) -> (String, String) {
+        let name_start = self.pos;
+        while self.pos < self.bytes.len() && is_tag_name_byte(self.bytes[self.pos]) {
+            self.pos += 1;
+        }
+        let name = self.src[name_start..self.pos].to_ascii_lowercase();
+
+        let save = self.pos;
+        self.skip_whitespace();
+        if self.peek_at(0) != Some(b'=') {
+            self.pos = save;
+            return (name, String::new());
+        }
+        self.pos += 1; // consume '='
+        self.skip_whitespace();
+
+        match self.peek_at(0) {
+            Some(q @ (b'"' | b'\'')) => {
+                self.pos += 1;
+                let value_start = self.pos;
+                while self.pos < self.bytes.len() && self.bytes[self.pos] != q {
+                    self.pos += 1;
+                }
+                let value = self.src[value_start..self.pos].to_string();
+                if self.pos < self.bytes.len() {
+                    self.pos += 1; // consume closing quote
+                }
+                (name, value)
+            }
+            _ => {
+                let value_start = self.pos;
+                while self.pos < self.bytes.len()
+                    && !matches!(self.bytes[self.pos], b' ' | b'\t' | b'\n' | b'\r' | b'\x0C' | b'>')
+                {
+                    self.pos += 1;
+                }
+                (name, self.src[value_start..self.pos].to_string())
+            }
+        }
+    }
+
+    /// Advances up to (but not past) the next `>` or newline, whichever
+    /// comes first, so a bogus tag doesn't swallow the rest of the
+    /// document. Callers consume the `>` themselves when one was found.
+    fn recover_to_tag_end(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'>' && self.bytes[self.pos] != b'\n' {
+            self.pos += 1;
+        }
+    }
+
+    fn scan_end_tag(&mut self, start: usize) {
+        self.pos += 2; // consume '</'
+        let name_start = self.pos;
+        while self.pos < self.bytes.len() && is_tag_name_byte(self.bytes[self.pos]) {
+            self.pos += 1;
+        }
+        if self.pos == name_start {
+            self.tokens.push(Token::error(
+                start,
+                self.pos,
+                "bogus end tag: expected a tag name after '</'".to_string(),
+            ));
+            self.recover_to_tag_end();
+            if self.pos < self.bytes.len() && self.bytes[self.pos] == b'>' {
+                self.pos += 1;
+            }
+            return;
+        }
+        let name = self.src[name_start..self.pos].to_ascii_lowercase();
+        self.skip_whitespace();
+        match self.peek_at(0) {
+            Some(b'>') => {
+                self.pos += 1;
+                self.tokens.push(Token::tag(TokenKind::EndTag, start, self.pos, name, Vec::new()));
+            }
+            _ => {
+                self.recover_to_tag_end();
+                if self.pos < self.bytes.len() && self.bytes[self.pos] == b'>' {
+                    self.pos += 1;
+                }
+                self.tokens.push(Token::error(
+                    start,
+                    self.pos,
+                    format!("unterminated end tag </{name}>: expected '>'"),
+                ));
+                self.tokens.push(Token::tag(TokenKind::EndTag, start, self.pos, name, Vec::new()));
+            }
+        }
+    }
+
+    /// Handles everything that starts `<!`: comments, doctypes, and
+    /// bogus declarations.
+    fn scan_declaration(&mut self, start: usize) {
+        if self.src[self.pos..].starts_with("<!--") {
+            self.pos += 4;
+            if let Some(end) = self.src[self.pos..].find("-->") {
+                self.pos += end + 3;
+                self.tokens.push(Token::simple(TokenKind::Comment, start, self.pos));
+            } else {
+                self.pos = self.bytes.len();
+                self.tokens.push(Token::error(start, self.pos, "unterminated comment: expected '-->'".to_string()));
+            }
+            return;
+        }
+
+        if self.bytes.get(self.pos..self.pos + 9).is_some_and(|s| s.eq_ignore_ascii_case(b"<!doctype")) {
+            self.pos += 9;
+            self.skip_whitespace();
+            let name_start = self.pos;
+            while self.pos < self.bytes.len() && self.bytes[self.pos] != b'>' {
+                self.pos += 1;
+            }
+            let _ = &self.src[name_start..self.pos];
+            if self.pos < self.bytes.len() {
+                self.pos += 1; // consume '>'
+                self.tokens.push(Token::simple(TokenKind::Doctype, start, self.pos));
+            } else {
+                self.tokens.push(Token::error(start, self.pos, "unterminated doctype: expected '>'".to_string()));
+            }
+            return;
+        }
+
+        // Bogus declaration: anything else starting with `<!`. Per the
+        // resynchronization rule, consume the rest up to `>` as a single
+        // Error token rather than failing the whole parse.
+        self.pos += 2;
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'>' {
+            self.pos += 1;
+        }
+        if self.pos < self.bytes.len() {
+            self.pos += 1;
+        }
+        self.tokens.push(Token::error(start, self.pos, "bogus declaration: expected comment or DOCTYPE".to_string()));
+    }
+
+    fn scan_processing_instruction(&mut self, start: usize) {
+        self.pos += 2; // consume '<?'
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'>' {
+            self.pos += 1;
+        }
+        if self.pos < self.bytes.len() {
+            self.pos += 1;
+            self.tokens.push(Token::simple(TokenKind::ProcessingInstruction, start, self.pos));
+        } else {
+            self.tokens.push(Token::error(
+                start,
+                self.pos,
+                "unterminated processing instruction: expected '>'".to_string(),
+            ));
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+}
+
+fn is_tag_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b':' || b == b'_' || b == b'.'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str) -> Vec<Token> {
+        Tokenizer::new(source).run()
+    }
+
+    #[test]
+    fn tokenizes_a_well_formed_tag_and_its_attributes() {
+        let tokens = run(r#"<div class="a b" id='x'>hi</div>"#);
+        assert_eq!(tokens[0].kind, TokenKind::StartTag);
+        assert_eq!(tokens[0].name.as_deref(), Some("div"));
+        assert_eq!(
+            tokens[0].attrs,
+            vec![("class".to_string(), "a b".to_string()), ("id".to_string(), "x".to_string())]
+        );
+        assert_eq!(tokens[1].kind, TokenKind::Text);
+        assert_eq!(source_slice(r#"<div class="a b" id='x'>hi</div>"#, &tokens[1]), "hi");
+        assert_eq!(tokens[2].kind, TokenKind::EndTag);
+        assert_eq!(tokens[2].name.as_deref(), Some("div"));
+    }
+
+    fn source_slice<'a>(source: &'a str, token: &Token) -> &'a str {
+        &source[token.start..token.end]
+    }
+
+    #[test]
+    fn recovers_from_an_unclosed_tag_instead_of_aborting() {
+        let tokens = run("<div><unclosed>text");
+        // No Error token here: running off the end of input inside a
+        // start tag produces an Error, but `<unclosed>` *is* terminated
+        // by its own `>`, so parsing continues and the trailing text is
+        // still tokenized.
+        assert!(tokens.iter().any(|t| t.kind == TokenKind::Text));
+        assert!(!tokens.iter().any(|t| t.kind == TokenKind::Error));
+    }
+
+    #[test]
+    fn reports_and_recovers_from_a_truly_unterminated_tag() {
+        let tokens = run("<div class=\"a");
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Error);
+    }
+
+    #[test]
+    fn treats_a_lone_angle_bracket_as_literal_text() {
+        let tokens = run("a < b");
+        assert!(tokens.iter().all(|t| t.kind == TokenKind::Text));
+        let rebuilt: String = tokens.iter().map(|t| &"a < b"[t.start..t.end]).collect();
+        assert_eq!(rebuilt, "a < b");
+    }
+
+    #[test]
+    fn bogus_attribute_punctuation_resyncs_at_the_tag_end() {
+        let tokens = run("<div =bad attr>content</div>");
+        assert_eq!(tokens[0].kind, TokenKind::Error);
+        assert_eq!(tokens[1].kind, TokenKind::StartTag);
+        assert_eq!(tokens[1].name.as_deref(), Some("div"));
+    }
+
+    #[test]
+    fn doctype_sniff_does_not_panic_on_non_ascii_after_bang() {
+        // Regression test: slicing `&str` at a byte offset that lands
+        // mid-UTF-8-sequence panics; the doctype sniff must stay
+        // byte-oriented so malformed markup like this degrades to a
+        // bogus-declaration error instead of crashing the whole parse.
+        let tokens = run("<!éééé");
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Error);
+    }
+
+    #[test]
+    fn parses_doctype_and_comment() {
+        let tokens = run("<!-- hi --><!DOCTYPE html><p>x");
+        assert_eq!(tokens[0].kind, TokenKind::Comment);
+        assert_eq!(tokens[1].kind, TokenKind::Doctype);
+        assert_eq!(tokens[2].kind, TokenKind::StartTag);
+    }
+}
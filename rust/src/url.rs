@@ -0,0 +1,237 @@
+//! Query-string normalization for URL-valued attributes.
+//!
+//! Backs a lint/format rule that canonicalizes `href`, `src`, and
+//! `action` attribute values (and, one entry at a time, the individual
+//! URLs inside a `srcset` list) so they come out identical across
+//! template renders: the URL is split into scheme/authority/path/query/
+//! fragment, each query key and value is percent-decoded and then
+//! re-encoded the same way every time, and the parameters can optionally
+//! be stably sorted by key. Values containing template expressions are
+//! left completely untouched, since we can't safely reparse or reorder
+//! text that isn't valid URL syntax yet.
+
+use pyo3::prelude::*;
+
+const TEMPLATE_DELIMITERS: [&str; 4] = ["{{", "}}", "{%", "%}"];
+
+/// Canonicalizes the URL in `value`. When `sort_params` is true, query
+/// parameters are stably sorted by key, preserving the relative order of
+/// duplicate keys; when false, their original order is kept.
+#[pyfunction]
+pub fn normalize_url_attr(value: &str, sort_params: bool) -> PyResult<String> {
+    if contains_template_expression(value) {
+        return Ok(value.to_string());
+    }
+
+    let (before_fragment, fragment) = split_once_keep(value, '#');
+    let (before_query, query) = split_once_keep(before_fragment, '?');
+    let (head, path) = split_scheme_and_authority(before_query);
+
+    let mut out = String::with_capacity(value.len());
+    out.push_str(head);
+    out.push_str(path);
+    if let Some(query) = query {
+        out.push('?');
+        out.push_str(&normalize_query(query, sort_params));
+    }
+    if let Some(fragment) = fragment {
+        out.push('#');
+        out.push_str(fragment);
+    }
+    Ok(out)
+}
+
+fn contains_template_expression(value: &str) -> bool {
+    TEMPLATE_DELIMITERS.iter().any(|delim| value.contains(delim))
+}
+
+/// Splits `s` on the first occurrence of `sep`, returning the part
+/// before it and, if `sep` was present, the part after.
+fn split_once_keep(s: &str, sep: char) -> (&str, Option<&str>) {
+    match s.find(sep) {
+        Some(i) => (&s[..i], Some(&s[i + sep.len_utf8()..])),
+        None => (s, None),
+    }
+}
+
+/// Splits off the scheme and authority (e.g. `https://example.com`,
+/// `//example.com`, or nothing for a relative URL) from the remaining
+/// path, so the path can be left untouched while the query is
+/// normalized.
+fn split_scheme_and_authority(s: &str) -> (&str, &str) {
+    if let Some(scheme_end) = s.find("://") {
+        // A scheme is only valid if everything before `://` looks like
+        // `[a-zA-Z][a-zA-Z0-9+.-]*`; otherwise `://` just happens to
+        // appear inside what we should treat as a relative path.
+        let candidate = &s[..scheme_end];
+        if is_valid_scheme(candidate) {
+            let authority_start = scheme_end + 3;
+            let authority_end = s[authority_start..].find('/').map_or(s.len(), |i| authority_start + i);
+            return (&s[..authority_end], &s[authority_end..]);
+        }
+    }
+    if let Some(rest) = s.strip_prefix("//") {
+        let authority_end = rest.find('/').map_or(s.len(), |i| 2 + i);
+        return (&s[..authority_end], &s[authority_end..]);
+    }
+    ("", s)
+}
+
+fn is_valid_scheme(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+}
+
+/// A single `key=value` (or bare `key`) query parameter, decoded.
+struct Param {
+    key: String,
+    /// `None` means the parameter had no `=` at all (e.g. bare `debug`
+    /// in `?debug&page=2`); `Some(String::new())` means it had an `=`
+    /// with an empty value (`?debug=`). Re-encoding preserves the
+    /// distinction.
+    value: Option<String>,
+}
+
+fn normalize_query(query: &str, sort_params: bool) -> String {
+    if query.is_empty() {
+        return String::new();
+    }
+
+    let mut params: Vec<Param> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => Param { key: percent_decode(k), value: Some(percent_decode(v)) },
+            None => Param { key: percent_decode(pair), value: None },
+        })
+        .collect();
+
+    if sort_params {
+        params.sort_by(|a, b| a.key.cmp(&b.key));
+    }
+
+    params
+        .iter()
+        .map(|p| match &p.value {
+            Some(v) => format!("{}={}", percent_encode(&p.key), percent_encode(v)),
+            None => percent_encode(&p.key),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Decodes `%XX` escapes and treats `+` as a literal space, matching
+/// `application/x-www-form-urlencoded` query-string conventions.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b'%' if i + 2 < bytes.len() => match hex_byte(bytes[i + 1], bytes[i + 2]) {
+                Some(b) => {
+                    out.push(b);
+                    i += 3;
+                }
+                None => {
+                    out.push(bytes[i]);
+                    i += 1;
+                }
+            },
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn hex_byte(hi: u8, lo: u8) -> Option<u8> {
+    let hi = (hi as char).to_digit(16)?;
+    let lo = (lo as char).to_digit(16)?;
+    Some((hi * 16 + lo) as u8)
+}
+
+/// Percent-encodes every byte except the URI-unreserved set
+/// (`A-Za-z0-9-_.~`), so the same logical key/value always produces the
+/// same bytes on the wire regardless of how it was originally encoded.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(value: &str, sort_params: bool) -> String {
+        normalize_url_attr(value, sort_params).unwrap()
+    }
+
+    #[test]
+    fn sort_params_is_stable_across_duplicate_keys() {
+        // "a" appears twice; sorting by key must not reorder the two
+        // "a" occurrences relative to each other.
+        assert_eq!(normalize("/search?b=2&a=1&a=0", true), "/search?a=1&a=0&b=2");
+    }
+
+    #[test]
+    fn leaves_param_order_alone_when_sort_params_is_false() {
+        assert_eq!(normalize("/search?b=2&a=1&a=0", false), "/search?b=2&a=1&a=0");
+    }
+
+    #[test]
+    fn percent_decodes_then_re_encodes_keys_and_values_consistently() {
+        assert_eq!(normalize("/path?q=hello%20world", true), "/path?q=hello%20world");
+        assert_eq!(normalize("/path?q=hello+world", true), "/path?q=hello%20world");
+    }
+
+    #[test]
+    fn preserves_bare_keys_without_a_value() {
+        assert_eq!(normalize("/path?debug&q=1", true), "/path?debug&q=1");
+    }
+
+    #[test]
+    fn bails_out_untouched_on_template_expressions() {
+        let value = "{{ url_for('x') }}?b=2&a=1";
+        assert_eq!(normalize(value, true), value);
+    }
+
+    #[test]
+    fn handles_absolute_urls_with_scheme_and_authority() {
+        assert_eq!(
+            normalize("https://example.com/path?b=2&a=1", true),
+            "https://example.com/path?a=1&b=2"
+        );
+    }
+
+    #[test]
+    fn handles_protocol_relative_urls() {
+        assert_eq!(normalize("//cdn.example.com/x.png?v=2&u=1", true), "//cdn.example.com/x.png?u=1&v=2");
+    }
+
+    #[test]
+    fn preserves_fragments_and_relative_paths() {
+        assert_eq!(normalize("relative/path?z=1&y=2#frag", true), "relative/path?y=2&z=1#frag");
+    }
+
+    #[test]
+    fn leaves_urls_without_a_query_string_unchanged() {
+        assert_eq!(normalize("/just/a/path", true), "/just/a/path");
+    }
+}